@@ -0,0 +1,157 @@
+//! Loading of user-declared project-root markers from `projroot.toml`.
+
+use anyhow::Context;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use projroot::marker::{Marker, MarkerKind};
+
+#[derive(Debug, Deserialize, Default)]
+struct RawConfig {
+    #[serde(default)]
+    markers: Vec<RawMarker>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawMarker {
+    name: String,
+    kind: RawMarkerKind,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum RawMarkerKind {
+    File,
+    Dir,
+    Any,
+    Glob,
+}
+
+impl From<RawMarkerKind> for MarkerKind {
+    fn from(kind: RawMarkerKind) -> Self {
+        match kind {
+            RawMarkerKind::File => MarkerKind::File,
+            RawMarkerKind::Dir => MarkerKind::Dir,
+            RawMarkerKind::Any => MarkerKind::Any,
+            RawMarkerKind::Glob => MarkerKind::Glob,
+        }
+    }
+}
+
+/// Searches for `projroot.toml`, starting at `start` and walking up through
+/// its ancestors, then falling back to `$XDG_CONFIG_HOME/projroot.toml` (or
+/// `$HOME/.config/projroot.toml` if that variable is unset). Returns the
+/// markers declared by the first file found, or an empty list if none
+/// exists anywhere.
+pub(crate) fn load_markers(start: &Path) -> anyhow::Result<Vec<Marker>> {
+    let Some(path) = find_config_file(start) else {
+        return Ok(Vec::new());
+    };
+
+    let contents = std::fs::read_to_string(&path)
+        .with_context(|| format!("could not read {}", path.display()))?;
+    let raw: RawConfig = toml::from_str(&contents)
+        .with_context(|| format!("could not parse {}", path.display()))?;
+
+    Ok(raw
+        .markers
+        .into_iter()
+        .map(|m| Marker::new(m.name, m.kind.into()))
+        .collect())
+}
+
+fn find_config_file(start: &Path) -> Option<PathBuf> {
+    for dir in start.ancestors() {
+        let candidate = dir.join("projroot.toml");
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+    }
+
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+
+    config_home
+        .map(|dir| dir.join("projroot.toml"))
+        .filter(|path| path.is_file())
+}
+
+/// Splits a comma/whitespace separated list of marker declarations, honoring
+/// double-quoted entries that themselves contain commas or spaces. Modeled
+/// on Mercurial's `Config::get_list`.
+pub(crate) fn parse_marker_list(raw: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for c in raw.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ',' | ' ' | '\t' if !in_quotes => {
+                if !current.is_empty() {
+                    out.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        out.push(current);
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_marker_list_comma_and_whitespace() {
+        assert_eq!(
+            parse_marker_list("Cargo.toml, go.mod  package.json"),
+            vec!["Cargo.toml", "go.mod", "package.json"]
+        );
+    }
+
+    #[test]
+    fn test_parse_marker_list_quoted_entry() {
+        assert_eq!(
+            parse_marker_list(r#""my marker", other"#),
+            vec!["my marker", "other"]
+        );
+    }
+
+    #[test]
+    fn test_load_markers_missing_config_is_empty() -> anyhow::Result<()> {
+        let t = tempfile::tempdir()?;
+        assert!(load_markers(t.path())?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_markers_from_ancestor() -> anyhow::Result<()> {
+        let t = tempfile::tempdir()?;
+        std::fs::write(
+            t.path().join("projroot.toml"),
+            r#"
+            [[markers]]
+            name = "Cargo.toml"
+            kind = "file"
+
+            [[markers]]
+            name = "*.sln"
+            kind = "glob"
+            "#,
+        )?;
+        std::fs::create_dir(t.path().join("nested"))?;
+
+        let markers = load_markers(&t.path().join("nested"))?;
+        assert_eq!(markers.len(), 2);
+        assert_eq!(markers[0].name, "Cargo.toml");
+        assert_eq!(markers[0].kind, MarkerKind::File);
+        assert_eq!(markers[1].kind, MarkerKind::Glob);
+        Ok(())
+    }
+}
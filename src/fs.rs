@@ -0,0 +1,147 @@
+//! Filesystem operations abstracted behind a trait, so the root-finding
+//! logic can run against the real filesystem or an in-memory fake in tests.
+
+use std::collections::HashSet;
+use std::ffi::{OsStr, OsString};
+use std::path::Path;
+
+use anyhow::Context;
+
+use crate::marker::MarkerKind;
+
+/// A snapshot of a single directory's entries, split by whether each one is
+/// a file or a directory.
+#[derive(Debug, Default, Clone)]
+pub struct DirContents {
+    pub files: HashSet<OsString>,
+    pub dirs: HashSet<OsString>,
+}
+
+impl DirContents {
+    pub fn has_file(&self, name: &str) -> bool {
+        self.files.contains(OsStr::new(name))
+    }
+
+    pub fn has_dir(&self, name: &str) -> bool {
+        self.dirs.contains(OsStr::new(name))
+    }
+
+    pub fn has_kind(&self, name: &str, kind: MarkerKind) -> bool {
+        match kind {
+            MarkerKind::File => self.has_file(name),
+            MarkerKind::Dir => self.has_dir(name),
+            MarkerKind::Any => self.has_file(name) || self.has_dir(name),
+            MarkerKind::Glob => self
+                .files
+                .iter()
+                .chain(self.dirs.iter())
+                .any(|n| crate::marker::glob_match(name, n)),
+        }
+    }
+}
+
+/// Abstracts the filesystem calls `projroot` needs to find a project root:
+/// reading a directory's contents, and (to detect filesystem boundaries)
+/// looking up a device number for a path.
+pub trait Filesystem {
+    /// Reads `dir` exactly once. A directory that can't be read (missing,
+    /// permission denied, not a directory) is treated as empty rather than
+    /// propagating the error, since callers only care whether markers are
+    /// present.
+    fn dir_contents(&self, dir: &Path) -> DirContents;
+
+    /// Returns a device number for `path`, used to detect when a search has
+    /// crossed a filesystem boundary. Implementations that have no such
+    /// concept (or don't care) may return a constant.
+    fn dev(&self, path: &Path) -> anyhow::Result<u64>;
+}
+
+/// The real filesystem, backed by `std::fs`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OsFilesystem;
+
+impl Filesystem for OsFilesystem {
+    fn dir_contents(&self, dir: &Path) -> DirContents {
+        let mut contents = DirContents::default();
+
+        if let Ok(entries) = std::fs::read_dir(dir) {
+            for entry in entries.flatten() {
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+                // `file_type()` comes from the raw dirent (like `lstat`) and
+                // doesn't follow symlinks; `entry.metadata()` doesn't either,
+                // so resolve symlinked entries through `fs::metadata`
+                // (`stat`) instead, so a symlinked marker directory isn't
+                // missed.
+                let is_dir = if file_type.is_symlink() {
+                    std::fs::metadata(entry.path())
+                        .map(|m| m.is_dir())
+                        .unwrap_or(false)
+                } else {
+                    file_type.is_dir()
+                };
+                if is_dir {
+                    contents.dirs.insert(entry.file_name());
+                } else {
+                    contents.files.insert(entry.file_name());
+                }
+            }
+        }
+
+        contents
+    }
+
+    #[cfg(unix)]
+    fn dev(&self, path: &Path) -> anyhow::Result<u64> {
+        use std::os::unix::fs::MetadataExt;
+
+        Ok(std::fs::metadata(path)
+            .with_context(|| format!("could not stat {}", path.display()))?
+            .dev())
+    }
+
+    #[cfg(not(unix))]
+    fn dev(&self, _path: &Path) -> anyhow::Result<u64> {
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_os_filesystem_dir_contents() -> anyhow::Result<()> {
+        let t = tempfile::tempdir()?;
+        std::fs::create_dir(t.path().join(".git"))?;
+        std::fs::File::create(t.path().join("_FOSSIL_"))?;
+
+        let contents = OsFilesystem.dir_contents(t.path());
+        assert!(contents.has_dir(".git"));
+        assert!(!contents.has_file(".git"));
+        assert!(contents.has_file("_FOSSIL_"));
+        assert!(!contents.has_dir("_FOSSIL_"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_os_filesystem_dir_contents_missing_dir_is_empty() {
+        let contents = OsFilesystem.dir_contents(Path::new("/does/not/exist"));
+        assert!(contents.files.is_empty());
+        assert!(contents.dirs.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_os_filesystem_dir_contents_follows_symlinked_dir() -> anyhow::Result<()> {
+        let t = tempfile::tempdir()?;
+        std::fs::create_dir(t.path().join("real_git"))?;
+        std::os::unix::fs::symlink(t.path().join("real_git"), t.path().join(".git"))?;
+
+        let contents = OsFilesystem.dir_contents(t.path());
+        assert!(contents.has_dir(".git"));
+        assert!(!contents.has_file(".git"));
+        Ok(())
+    }
+}
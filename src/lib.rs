@@ -0,0 +1,371 @@
+//! Core project-root-finding logic for `projroot`, usable as a library
+//! independent of the CLI.
+
+use std::path::{Path, PathBuf};
+
+mod ancestors_same_filesystem;
+pub mod fs;
+pub mod marker;
+
+use fs::{Filesystem, OsFilesystem};
+use marker::Marker;
+
+#[derive(clap::ValueEnum, Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Mode {
+    #[default]
+    Closest,
+    Farthest,
+}
+
+/// Built-in VCS markers, each typed as the filesystem entry it must be so
+/// that, e.g., a stray file named `.git` doesn't falsely flag a root. `.git`
+/// itself is typed `Any`, since it's a directory in a normal checkout but a
+/// file (a `gitdir:` pointer) in a worktree or submodule. Fossil check-outs
+/// are recognized by the `.fslckout`/`_FOSSIL_` file it drops in its working
+/// directory, and Jujutsu by its `.jj` directory.
+const BUILTIN_MARKERS: &[(&str, marker::MarkerKind)] = &[
+    (".git", marker::MarkerKind::Any),
+    ("_darcs", marker::MarkerKind::Dir),
+    (".hg", marker::MarkerKind::Dir),
+    (".bzr", marker::MarkerKind::Dir),
+    (".svn", marker::MarkerKind::Dir),
+    (".jj", marker::MarkerKind::Dir),
+    (".fslckout", marker::MarkerKind::File),
+    ("_FOSSIL_", marker::MarkerKind::File),
+];
+
+/// If `dir` is a project root, returns the name and kind of the marker that
+/// matched there (checking the built-in markers before `extra_markers`).
+fn matching_marker(
+    filesystem: &dyn Filesystem,
+    dir: &Path,
+    extra_markers: &[Marker],
+) -> Option<(String, marker::MarkerKind)> {
+    let contents = filesystem.dir_contents(dir);
+
+    for (name, kind) in BUILTIN_MARKERS {
+        if contents.has_kind(name, *kind) {
+            return Some((name.to_string(), *kind));
+        }
+    }
+    for m in extra_markers {
+        if contents.has_kind(&m.name, m.kind) {
+            return Some((m.name.clone(), m.kind));
+        }
+    }
+    None
+}
+
+fn ancestors<'a>(
+    filesystem: &'a dyn Filesystem,
+    starting_directory: &'a Path,
+    span_file_systems: bool,
+) -> anyhow::Result<impl Iterator<Item = anyhow::Result<&'a Path>>> {
+    ancestors_same_filesystem::Ancestors::new(
+        starting_directory,
+        starting_directory.ancestors(),
+        span_file_systems,
+        filesystem,
+    )
+}
+
+/// A successful project-root search: the winning path, the marker that
+/// matched there, and every candidate root encountered along the way (in
+/// `Mode::Closest` this is always just the winner itself).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoundRoot {
+    pub path: PathBuf,
+    pub marker: String,
+    pub marker_kind: marker::MarkerKind,
+    pub candidates: Vec<PathBuf>,
+}
+
+fn find_project_root(
+    filesystem: &dyn Filesystem,
+    starting_directory: &Path,
+    span_file_systems: bool,
+    mode: Mode,
+    extra_markers: &[Marker],
+) -> anyhow::Result<Option<FoundRoot>> {
+    let mut last_match: Option<(PathBuf, String, marker::MarkerKind)> = None;
+    let mut candidates: Vec<PathBuf> = Vec::new();
+
+    for path in ancestors(filesystem, starting_directory, span_file_systems)? {
+        let path = path?;
+        if let Some((marker, marker_kind)) = matching_marker(filesystem, path, extra_markers) {
+            candidates.push(path.to_path_buf());
+            if mode == Mode::Closest {
+                return Ok(Some(FoundRoot {
+                    path: path.to_path_buf(),
+                    marker,
+                    marker_kind,
+                    candidates,
+                }));
+            }
+            last_match = Some((path.to_path_buf(), marker, marker_kind));
+        }
+    }
+
+    Ok(last_match.map(|(path, marker, marker_kind)| FoundRoot {
+        path,
+        marker,
+        marker_kind,
+        candidates,
+    }))
+}
+
+/// Builds a search for the nearest (or farthest) ancestor directory
+/// containing a project-root marker.
+///
+/// Defaults to `Mode::Closest`, stopping at filesystem boundaries, no extra
+/// markers beyond the built-in VCS ones, and the real filesystem. Use
+/// [`ProjectRootFinder::filesystem`] to swap in a fake for testing.
+pub struct ProjectRootFinder<'a> {
+    starting_directory: PathBuf,
+    mode: Mode,
+    span_file_systems: bool,
+    markers: Vec<Marker>,
+    filesystem: &'a dyn Filesystem,
+}
+
+impl ProjectRootFinder<'static> {
+    pub fn new(starting_directory: impl Into<PathBuf>) -> Self {
+        Self {
+            starting_directory: starting_directory.into(),
+            mode: Mode::Closest,
+            span_file_systems: false,
+            markers: Vec::new(),
+            filesystem: &OsFilesystem,
+        }
+    }
+}
+
+impl<'a> ProjectRootFinder<'a> {
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn span_file_systems(mut self, span_file_systems: bool) -> Self {
+        self.span_file_systems = span_file_systems;
+        self
+    }
+
+    pub fn markers(mut self, markers: Vec<Marker>) -> Self {
+        self.markers = markers;
+        self
+    }
+
+    pub fn filesystem(self, filesystem: &dyn Filesystem) -> ProjectRootFinder<'_> {
+        ProjectRootFinder {
+            starting_directory: self.starting_directory,
+            mode: self.mode,
+            span_file_systems: self.span_file_systems,
+            markers: self.markers,
+            filesystem,
+        }
+    }
+
+    pub fn find(&self) -> anyhow::Result<Option<FoundRoot>> {
+        find_project_root(
+            self.filesystem,
+            &self.starting_directory,
+            self.span_file_systems,
+            self.mode,
+            &self.markers,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An in-memory fake filesystem: a map from directory to its contents,
+    /// with every path reporting the same device number (i.e. one big
+    /// filesystem).
+    #[derive(Default)]
+    struct FakeFilesystem {
+        dirs: std::collections::HashMap<PathBuf, fs::DirContents>,
+    }
+
+    impl FakeFilesystem {
+        fn with_dir(mut self, path: impl Into<PathBuf>, files: &[&str], dirs: &[&str]) -> Self {
+            self.dirs.insert(
+                path.into(),
+                fs::DirContents {
+                    files: files.iter().copied().map(std::ffi::OsString::from).collect(),
+                    dirs: dirs.iter().copied().map(std::ffi::OsString::from).collect(),
+                },
+            );
+            self
+        }
+    }
+
+    impl Filesystem for FakeFilesystem {
+        fn dir_contents(&self, dir: &Path) -> fs::DirContents {
+            self.dirs.get(dir).cloned().unwrap_or_default()
+        }
+
+        fn dev(&self, _path: &Path) -> anyhow::Result<u64> {
+            Ok(0)
+        }
+    }
+
+    #[test]
+    fn test_find_with_fake_filesystem() -> anyhow::Result<()> {
+        let filesystem = FakeFilesystem::default()
+            .with_dir("/repo", &[], &[".git", "foo"])
+            .with_dir("/repo/foo", &[], &["bar"])
+            .with_dir("/repo/foo/bar", &[], &[]);
+
+        let found = ProjectRootFinder::new("/repo/foo/bar")
+            .filesystem(&filesystem)
+            .find()?;
+        assert_eq!(found.map(|f| f.path), Some(PathBuf::from("/repo")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_respects_extra_markers() -> anyhow::Result<()> {
+        let filesystem = FakeFilesystem::default()
+            .with_dir("/repo", &["Cargo.toml"], &["src"])
+            .with_dir("/repo/src", &[], &[]);
+
+        let not_found = ProjectRootFinder::new("/repo/src")
+            .filesystem(&filesystem)
+            .find()?;
+        assert_eq!(not_found, None);
+
+        let found = ProjectRootFinder::new("/repo/src")
+            .markers(vec![Marker::parse("Cargo.toml")])
+            .filesystem(&filesystem)
+            .find()?
+            .unwrap();
+        assert_eq!(found.path, PathBuf::from("/repo"));
+        assert_eq!(found.marker, "Cargo.toml");
+        assert_eq!(found.marker_kind, marker::MarkerKind::Any);
+        Ok(())
+    }
+
+    #[test]
+    fn test_matching_marker_git() -> anyhow::Result<()> {
+        let t = tempfile::tempdir()?;
+        assert_eq!(matching_marker(&OsFilesystem, t.path(), &[]), None);
+        std::fs::create_dir(t.path().join(".git"))?;
+        assert_eq!(
+            matching_marker(&OsFilesystem, t.path(), &[]),
+            Some((".git".to_string(), marker::MarkerKind::Any))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_matching_marker_git_worktree_file() -> anyhow::Result<()> {
+        // In a worktree or submodule, `.git` is a file (a `gitdir:`
+        // pointer) rather than a directory.
+        let t = tempfile::tempdir()?;
+        std::fs::write(t.path().join(".git"), "gitdir: /elsewhere/.git/worktrees/foo\n")?;
+        assert_eq!(
+            matching_marker(&OsFilesystem, t.path(), &[]),
+            Some((".git".to_string(), marker::MarkerKind::Any))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_matching_marker_svn() -> anyhow::Result<()> {
+        let t = tempfile::tempdir()?;
+        assert_eq!(matching_marker(&OsFilesystem, t.path(), &[]), None);
+        std::fs::create_dir(t.path().join(".svn"))?;
+        assert!(matching_marker(&OsFilesystem, t.path(), &[]).is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_matching_marker_jujutsu() -> anyhow::Result<()> {
+        let t = tempfile::tempdir()?;
+        assert_eq!(matching_marker(&OsFilesystem, t.path(), &[]), None);
+        std::fs::create_dir(t.path().join(".jj"))?;
+        assert!(matching_marker(&OsFilesystem, t.path(), &[]).is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_project_root_fossil_nested() -> anyhow::Result<()> {
+        let t = tempfile::tempdir()?;
+        std::fs::File::create(t.path().join("_FOSSIL_"))?;
+        std::fs::create_dir_all(t.path().join("foo").join("bar"))?;
+
+        let root = find_project_root(
+            &OsFilesystem,
+            &t.path().join("foo").join("bar"),
+            false,
+            Mode::Closest,
+            &[],
+        )?
+        .unwrap();
+        assert_eq!(root.path, t.path().to_owned());
+        assert_eq!(root.marker, "_FOSSIL_");
+        assert_eq!(root.marker_kind, marker::MarkerKind::File);
+        Ok(())
+    }
+
+    #[test]
+    fn test_matching_marker_fossil_file_not_dir() -> anyhow::Result<()> {
+        let t = tempfile::tempdir()?;
+        // A directory coincidentally named `_FOSSIL_` must not count as the
+        // file Fossil actually drops.
+        std::fs::create_dir(t.path().join("_FOSSIL_"))?;
+        assert_eq!(matching_marker(&OsFilesystem, t.path(), &[]), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_matching_marker_extra_marker() -> anyhow::Result<()> {
+        let t = tempfile::tempdir()?;
+        let markers = [Marker::parse("Cargo.toml")];
+        assert_eq!(matching_marker(&OsFilesystem, t.path(), &markers), None);
+        std::fs::File::create(t.path().join("Cargo.toml"))?;
+        assert_eq!(
+            matching_marker(&OsFilesystem, t.path(), &markers),
+            Some(("Cargo.toml".to_string(), marker::MarkerKind::Any))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_project_root_mode() -> anyhow::Result<()> {
+        let t = tempfile::tempdir()?;
+        std::fs::create_dir(t.path().join(".git"))?;
+        std::fs::create_dir(t.path().join("foo"))?;
+        std::fs::create_dir(t.path().join("foo").join("bar"))?;
+        std::fs::create_dir(t.path().join("foo").join("bar").join(".git"))?;
+
+        let closest = find_project_root(
+            &OsFilesystem,
+            &t.path().join("foo").join("bar"),
+            false,
+            Mode::Closest,
+            &[],
+        )?
+        .unwrap();
+        assert_eq!(closest.path, t.path().join("foo").join("bar"));
+        assert_eq!(closest.candidates, vec![t.path().join("foo").join("bar")]);
+
+        let farthest = find_project_root(
+            &OsFilesystem,
+            &t.path().join("foo").join("bar"),
+            false,
+            Mode::Farthest,
+            &[],
+        )?
+        .unwrap();
+        assert_eq!(farthest.path, t.path().to_owned());
+        assert_eq!(
+            farthest.candidates,
+            vec![t.path().join("foo").join("bar"), t.path().to_owned()]
+        );
+        Ok(())
+    }
+}
@@ -2,13 +2,18 @@ use anyhow::Context;
 use clap::{Parser, ValueEnum};
 use std::path::{Path, PathBuf};
 
-#[cfg(unix)]
-mod ancestors_same_filesystem;
-
-#[derive(ValueEnum, Debug, PartialEq, Eq, Clone, Copy)]
-enum Mode {
-    Closest,
-    Farthest,
+use projroot::marker::{Marker, MarkerKind};
+use projroot::{FoundRoot, Mode, ProjectRootFinder};
+
+mod config;
+
+#[derive(ValueEnum, Debug, Default, PartialEq, Eq, Clone, Copy)]
+enum Format {
+    #[default]
+    Absolute,
+    Relative,
+    Marker,
+    Json,
 }
 
 #[derive(Debug, Parser)]
@@ -29,117 +34,151 @@ struct Args {
     workdir: Option<PathBuf>,
     #[clap(short, long, value_enum, default_value_t = Mode::Closest)]
     mode: Mode,
+    #[clap(
+        long,
+        help = "Additional marker to treat as a project root indicator (comma/whitespace separated list; may be given more than once). Matches whether the entry is a file or a directory unless it ends in '/' (directory only) or contains '*'/'?' (glob)"
+    )]
+    marker: Vec<String>,
+    #[clap(
+        long,
+        value_enum,
+        default_value_t = Format::Absolute,
+        help = "How to print the found root: absolute path, relative to the starting directory, the matched marker name, or a JSON object"
+    )]
+    format: Format,
+    #[clap(
+        long,
+        alias = "no-canonicalize",
+        help = "Don't canonicalize the starting directory; walk it as navigated (honoring $PWD) instead of resolving symlinks"
+    )]
+    logical: bool,
 }
 
-const INDICATORS: &[&str] = &[".git", "_darcs", ".hg", ".bzr", ".svn"];
+#[derive(serde::Serialize)]
+struct JsonOutput {
+    root: String,
+    marker: String,
+    marker_kind: &'static str,
+    candidates: Vec<String>,
+}
 
-fn is_project_root<P: AsRef<Path>>(dir: &P) -> bool {
-    let p = dir.as_ref();
+fn marker_kind_str(kind: MarkerKind) -> &'static str {
+    match kind {
+        MarkerKind::File => "file",
+        MarkerKind::Dir => "dir",
+        MarkerKind::Any => "any",
+        MarkerKind::Glob => "glob",
+    }
+}
 
-    INDICATORS.iter().any(|i| p.join(i).exists())
+/// Expresses `target` (assumed absolute, as is `base`) relative to `base`.
+fn relative_to(base: &Path, target: &Path) -> PathBuf {
+    let base_components: Vec<_> = base.components().collect();
+    let target_components: Vec<_> = target.components().collect();
+    let common = base_components
+        .iter()
+        .zip(target_components.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut relative = PathBuf::new();
+    for _ in common..base_components.len() {
+        relative.push("..");
+    }
+    for component in &target_components[common..] {
+        relative.push(component.as_os_str());
+    }
+    if relative.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        relative
+    }
 }
 
-#[inline(always)]
-#[allow(unused_variables)]
-fn ancestors(
-    starting_directory: &Path,
-    span_file_systems: bool,
-) -> anyhow::Result<impl Iterator<Item = anyhow::Result<&Path>>> {
-    cfg_if::cfg_if! {
-        if #[cfg(unix)] {
-            ancestors_same_filesystem::Ancestors::new(starting_directory, starting_directory.ancestors(), span_file_systems)
-        } else {
-            Ok(starting_directory.ancestors().map(|i| Ok(i)))
+fn print_found_root(found: &FoundRoot, format: Format, starting_directory: &Path) -> anyhow::Result<()> {
+    match format {
+        Format::Absolute => println!("{}", found.path.as_os_str().to_string_lossy()),
+        Format::Relative => println!(
+            "{}",
+            relative_to(starting_directory, &found.path)
+                .as_os_str()
+                .to_string_lossy()
+        ),
+        Format::Marker => println!("{}", found.marker),
+        Format::Json => {
+            let output = JsonOutput {
+                root: found.path.to_string_lossy().into_owned(),
+                marker: found.marker.clone(),
+                marker_kind: marker_kind_str(found.marker_kind),
+                candidates: found
+                    .candidates
+                    .iter()
+                    .map(|p| p.to_string_lossy().into_owned())
+                    .collect(),
+            };
+            println!(
+                "{}",
+                serde_json::to_string(&output).context("could not serialize output as JSON")?
+            );
         }
     }
+    Ok(())
 }
 
-fn find_project_root(
-    starting_directory: &Path,
-    span_file_systems: bool,
-    mode: Mode,
-) -> anyhow::Result<Option<PathBuf>> {
-    let mut last_candidate: Option<PathBuf> = None;
-
-    for path in ancestors(starting_directory, span_file_systems)? {
-        let path = path?;
-        if is_project_root(&path) {
-            if mode == Mode::Closest {
-                return Ok(Some(path.to_path_buf()));
-            } else {
-                last_candidate.replace(path.to_owned());
-            }
+/// Returns the cwd as the user navigated to it: `$PWD`, if it's set and
+/// actually resolves to the real working directory (so it can carry
+/// symlinked path components the canonical form would otherwise erase),
+/// falling back to `std::env::current_dir()`.
+fn logical_cwd() -> anyhow::Result<PathBuf> {
+    let cwd = std::env::current_dir().context("could not determine cwd")?;
+
+    if let Some(pwd) = std::env::var_os("PWD") {
+        let pwd = PathBuf::from(pwd);
+        if std::fs::canonicalize(&pwd).ok().as_deref() == Some(cwd.as_path()) {
+            return Ok(pwd);
         }
     }
-    if let Some(path) = last_candidate {
-        Ok(Some(path))
-    } else {
-        Ok(None)
-    }
+
+    Ok(cwd)
 }
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
 
-    let starting_directory = args
-        .workdir
-        .unwrap_or(std::env::current_dir().context("could not determine cwd")?);
+    let starting_directory = match args.workdir.clone() {
+        Some(dir) => dir,
+        None => logical_cwd()?,
+    };
 
     #[cfg(not(target_arch = "wasm32"))]
-    let starting_directory =
-        std::fs::canonicalize(starting_directory).context("could not canonicalize path")?;
+    let starting_directory = if args.logical {
+        starting_directory
+    } else {
+        std::fs::canonicalize(starting_directory).context("could not canonicalize path")?
+    };
 
     #[cfg(unix)]
     let span_file_systems = args.span_file_systems;
     #[cfg(not(unix))]
     let span_file_systems = true;
 
-    if let Some(path) = find_project_root(&starting_directory, span_file_systems, args.mode)? {
-        println!("{}", path.as_os_str().to_string_lossy());
-        Ok(())
-    } else {
+    let mut extra_markers = config::load_markers(&starting_directory)?;
+    for raw in &args.marker {
+        extra_markers.extend(config::parse_marker_list(raw).iter().map(|s| Marker::parse(s)));
+    }
+
+    let found = ProjectRootFinder::new(starting_directory.clone())
+        .mode(args.mode)
+        .span_file_systems(span_file_systems)
+        .markers(extra_markers)
+        .find()?;
+
+    let Some(found) = found else {
         anyhow::bail!(
             "found no project root in ancestors of {}",
             starting_directory.as_os_str().to_string_lossy()
         );
-    }
-}
+    };
 
-#[cfg(test)]
-mod tests {
-    use super::{find_project_root, is_project_root, Mode};
-
-    #[test]
-    fn test_is_project_root_git() -> anyhow::Result<()> {
-        let t = tempfile::tempdir()?;
-        assert!(!is_project_root(&t.path()));
-        std::fs::create_dir(t.path().join(".git"))?;
-        assert!(is_project_root(&t.path()));
-        Ok(())
-    }
-
-    #[test]
-    fn test_is_project_root_svn() -> anyhow::Result<()> {
-        let t = tempfile::tempdir()?;
-        assert!(!is_project_root(&t.path()));
-        std::fs::create_dir(t.path().join(".svn"))?;
-        assert!(is_project_root(&t.path()));
-        Ok(())
-    }
-
-    #[test]
-    fn test_find_project_root_mode() -> anyhow::Result<()> {
-        let t = tempfile::tempdir()?;
-        std::fs::create_dir(t.path().join(".git"))?;
-        std::fs::create_dir(t.path().join("foo"))?;
-        std::fs::create_dir(t.path().join("foo").join("bar"))?;
-        std::fs::create_dir(t.path().join("foo").join("bar").join(".git"))?;
-
-        let closest = find_project_root(&t.path().join("foo").join("bar"), false, Mode::Closest)?;
-        assert_eq!(closest, Some(t.path().join("foo").join("bar")));
-
-        let farthest = find_project_root(&t.path().join("foo").join("bar"), false, Mode::Farthest)?;
-        assert_eq!(farthest, Some(t.path().to_owned()));
-        Ok(())
-    }
+    print_found_root(&found, args.format, &starting_directory)
 }
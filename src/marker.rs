@@ -0,0 +1,91 @@
+use std::ffi::OsStr;
+
+/// The declared shape of a project-root marker: whether it must be a file,
+/// must be a directory, may be either (e.g. `.git`, which is a directory in
+/// a normal checkout but a file pointing elsewhere in a worktree or
+/// submodule), or is matched as a glob pattern against every entry in a
+/// candidate directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkerKind {
+    File,
+    Dir,
+    Any,
+    Glob,
+}
+
+/// A single user- or config-declared project-root marker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Marker {
+    pub name: String,
+    pub kind: MarkerKind,
+}
+
+impl Marker {
+    pub fn new(name: impl Into<String>, kind: MarkerKind) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+        }
+    }
+
+    /// Parses a single `--marker` entry. A trailing `/` forces `Dir`, a name
+    /// containing `*` or `?` is treated as a `Glob`, and everything else
+    /// defaults to `Any` (matching whether the entry turns out to be a file
+    /// or a directory), since a bare name like `node_modules` or `.hg` is
+    /// just as likely to name a directory as `Cargo.toml` is to name a file.
+    pub fn parse(raw: &str) -> Self {
+        if let Some(name) = raw.strip_suffix('/') {
+            Self::new(name, MarkerKind::Dir)
+        } else if raw.contains(['*', '?']) {
+            Self::new(raw, MarkerKind::Glob)
+        } else {
+            Self::new(raw, MarkerKind::Any)
+        }
+    }
+}
+
+/// Minimal glob matching supporting `*` (any run of characters) and `?`
+/// (exactly one character), anchored to the full candidate name.
+pub(crate) fn glob_match(pattern: &str, candidate: &OsStr) -> bool {
+    match candidate.to_str() {
+        Some(candidate) => {
+            let pattern: Vec<char> = pattern.chars().collect();
+            let text: Vec<char> = candidate.chars().collect();
+            glob_match_chars(&pattern, &text)
+        }
+        None => false,
+    }
+}
+
+fn glob_match_chars(pattern: &[char], text: &[char]) -> bool {
+    match (pattern.first(), text.first()) {
+        (None, None) => true,
+        (Some('*'), _) => {
+            glob_match_chars(&pattern[1..], text)
+                || (!text.is_empty() && glob_match_chars(pattern, &text[1..]))
+        }
+        (Some('?'), Some(_)) => glob_match_chars(&pattern[1..], &text[1..]),
+        (Some(p), Some(t)) if p == t => glob_match_chars(&pattern[1..], &text[1..]),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_infers_kind() {
+        assert_eq!(Marker::parse("Cargo.toml").kind, MarkerKind::Any);
+        assert_eq!(Marker::parse(".hg").kind, MarkerKind::Any);
+        assert_eq!(Marker::parse(".git/").kind, MarkerKind::Dir);
+        assert_eq!(Marker::parse("*.sln").kind, MarkerKind::Glob);
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.sln", OsStr::new("project.sln")));
+        assert!(glob_match("go.???", OsStr::new("go.mod")));
+        assert!(!glob_match("*.sln", OsStr::new("project.toml")));
+    }
+}